@@ -0,0 +1,207 @@
+//! Shared types and the `config!`/`config_enum!` macros used to generate `Element`-implementing
+//! structs and enums.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Metadata gathered while loading a `Config` from disk.
+///
+/// Every `config!`-generated struct carries one of these, populated as the value tree is walked.
+/// It currently tracks where the value itself came from, and where any `"extern"` fields were
+/// pulled in from, so that `config::watch` can find every file a load touched. `write_file` does
+/// not use it: extern sections are written inline into the single file it is given.
+#[derive(Clone, Debug, Default)]
+pub struct ConfigMeta {
+    /// Path the value itself was loaded from, if any. When `Element::from_file` fell back to an
+    /// embedded default, this is the on-disk path that was requested and not found.
+    pub path: Option<PathBuf>,
+    /// Every file pulled in through a `"extern"` field while loading this value, at any depth
+    /// (an extern file that itself has an extern field contributes that file too). Flat and
+    /// unkeyed, since two different sections may use the same field name for an extern.
+    pub extern_paths: Vec<PathBuf>,
+    /// The env var prefix this value was loaded with, if it was loaded through
+    /// `Element::from_file_with_env`.
+    pub env_prefix: Option<String>,
+    /// The full ordered list of source files this value was assembled from, if it was loaded
+    /// through `Element::from_sources`. Empty otherwise; `path` holds the single source in that
+    /// case.
+    pub source_paths: Vec<PathBuf>,
+}
+
+/// Errors that can occur while loading, merging, or writing a `Config`.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// An IO error occurred while reading or writing a file.
+    FileError(io::Error),
+    /// The file's contents could not be parsed by the selected format.
+    ParserError(String),
+    /// A `${NAME}` placeholder in a string value named an environment variable that isn't set, or
+    /// was never closed with `}`.
+    InterpolationError(String),
+    /// The file's extension didn't match any known format.
+    ExtensionError,
+    /// The value tree could not be serialized back out, or `Element::watch` could not set up its
+    /// file watcher.
+    WriteError(String),
+    /// A dotted/subscript path passed to `Element::get`/`set` didn't resolve inside the value.
+    PathError(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::FileError(ref err) => write!(f, "File error: {}", err),
+            ConfigError::ParserError(ref msg) => write!(f, "Parser error: {}", msg),
+            ConfigError::InterpolationError(ref name) => {
+                write!(f, "Unresolved interpolation placeholder: ${{{}}}", name)
+            }
+            ConfigError::ExtensionError => write!(f, "Unrecognised or missing file extension"),
+            ConfigError::WriteError(ref msg) => write!(f, "Write error: {}", msg),
+            ConfigError::PathError(ref path) => write!(f, "Path not found: {}", path),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn description(&self) -> &str {
+        match *self {
+            ConfigError::FileError(_) => "file error",
+            ConfigError::ParserError(_) => "parser error",
+            ConfigError::InterpolationError(_) => "interpolation error",
+            ConfigError::ExtensionError => "extension error",
+            ConfigError::WriteError(_) => "write error",
+            ConfigError::PathError(_) => "path error",
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::FileError(err)
+    }
+}
+
+/// Declares a struct that implements [`Element`](trait.Element.html), and whose fields can each
+/// be loaded from a config file, falling back to the given default when the field is absent or
+/// of the wrong type.
+///
+/// Passing `from_embedded "path/to/default.yml",` right after the name bakes that file's bytes
+/// into the binary via `include_bytes!`, and `Element::from_file` transparently falls back to
+/// parsing them when the requested on-disk file doesn't exist.
+///
+/// See the [module level documentation](index.html) for usage.
+#[macro_export]
+macro_rules! config {
+    ($name:ident from_embedded $embedded_path:expr, { $( $field:ident : $ty:ty = $default:expr, )* }) => {
+        __config_decl!(
+            $name,
+            Some(($embedded_path, include_bytes!($embedded_path))),
+            { $( $field : $ty = $default, )* }
+        );
+    };
+    ($name:ident { $( $field:ident : $ty:ty = $default:expr, )* }) => {
+        __config_decl!($name, None, { $( $field : $ty = $default, )* });
+    };
+}
+
+/// Shared codegen for both forms of `config!`; not meant to be used directly.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __config_decl {
+    ($name:ident, $embedded:expr, { $( $field:ident : $ty:ty = $default:expr, )* }) => {
+        #[derive(Clone, Debug)]
+        pub struct $name {
+            $( pub $field: $ty, )*
+            /// Metadata about how this value was loaded.
+            pub meta: $crate::config::ConfigMeta,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name {
+                    $( $field: $default, )*
+                    meta: $crate::config::ConfigMeta::default(),
+                }
+            }
+        }
+
+        impl $crate::config::Element for $name {
+            fn from_value(value: &$crate::config::Value, meta: $crate::config::ConfigMeta) -> Self {
+                let mut result = $name::default();
+                result.meta = meta;
+                if let Some(map) = value.as_map() {
+                    $(
+                        if let Some(field_value) = $crate::config::Value::map_get(map, stringify!($field)) {
+                            result.$field = $crate::config::Element::from_value(
+                                field_value,
+                                $crate::config::ConfigMeta::default(),
+                            );
+                        }
+                    )*
+                }
+                result
+            }
+
+            fn to_value(&self) -> $crate::config::Value {
+                let mut map = Vec::new();
+                $(
+                    map.push((
+                        stringify!($field).to_string(),
+                        $crate::config::Element::to_value(&self.$field),
+                    ));
+                )*
+                $crate::config::Value::Map(map)
+            }
+
+            fn config_meta(&self) -> $crate::config::ConfigMeta {
+                self.meta.clone()
+            }
+
+            fn embedded_default() -> Option<(&'static str, &'static [u8])> {
+                $embedded
+            }
+        }
+    };
+}
+
+/// Declares a plain enum that implements [`Element`](trait.Element.html) by converting to and
+/// from its variant names as strings. Does not support data-carrying variants.
+#[macro_export]
+macro_rules! config_enum {
+    ($name:ident { $( $variant:ident, )* }) => {
+        #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+        pub enum $name {
+            $( $variant, )*
+        }
+
+        impl $crate::config::Element for $name {
+            fn from_value(value: &$crate::config::Value, _meta: $crate::config::ConfigMeta) -> Self {
+                if let Some(name) = value.as_str() {
+                    $(
+                        if name == stringify!($variant) {
+                            return $name::$variant;
+                        }
+                    )*
+                }
+                // Fall back to the first variant when the value is missing or unrecognised.
+                $name::first_variant()
+            }
+
+            fn to_value(&self) -> $crate::config::Value {
+                let name = match *self {
+                    $( $name::$variant => stringify!($variant), )*
+                };
+                $crate::config::Value::String(name.to_string())
+            }
+        }
+
+        impl $name {
+            fn first_variant() -> Self {
+                let mut variants = vec![$( $name::$variant, )*];
+                variants.remove(0)
+            }
+        }
+    };
+}
@@ -0,0 +1,342 @@
+//! Serialization backends for the config system.
+//!
+//! A `Format` knows how to turn raw bytes from a file into the format-agnostic
+//! [`Value`](enum.Value.html) tree, and back again. Which `Format` applies to a given file is
+//! chosen by its extension (see [`format_for_extension`](fn.format_for_extension.html)), which is
+//! how a single `config!`-generated struct can be loaded from a `.yml`, `.toml`, or `.json` file
+//! interchangeably.
+
+extern crate serde_json;
+extern crate toml;
+extern crate yaml_rust;
+
+use std::collections::BTreeMap;
+
+use self::yaml_rust::{Yaml, YamlLoader, YamlEmitter};
+
+use config::definitions::ConfigError;
+use config::value::Value;
+
+/// File extensions understood by one of the built-in formats, in the order they are tried.
+pub const EXTENSIONS: &'static [&'static str] = &["yml", "yaml", "toml", "json"];
+
+/// Parses bytes into a [`Value`](enum.Value.html) tree, and emits a `Value` tree back into bytes.
+pub trait Format {
+    /// Parses raw file contents into a format-agnostic value tree.
+    fn parse(&self, bytes: &[u8]) -> Result<Value, ConfigError>;
+    /// Serializes a value tree back into this format's on-disk representation.
+    fn emit(&self, value: &Value) -> Result<Vec<u8>, ConfigError>;
+}
+
+/// Picks the built-in `Format` matching a file extension ("yml"/"yaml", "toml", or "json"),
+/// ignoring case.
+pub fn format_for_extension(ext: &str) -> Option<Box<Format>> {
+    match ext.to_lowercase().as_str() {
+        "yml" | "yaml" => Some(Box::new(YamlFormat)),
+        "toml" => Some(Box::new(TomlFormat)),
+        "json" => Some(Box::new(JsonFormat)),
+        _ => None,
+    }
+}
+
+/// The original, and still default, `.yml`/`.yaml` backend.
+pub struct YamlFormat;
+
+impl Format for YamlFormat {
+    fn parse(&self, bytes: &[u8]) -> Result<Value, ConfigError> {
+        let text = String::from_utf8_lossy(bytes);
+        let mut docs = YamlLoader::load_from_str(&text)
+            .map_err(|e| ConfigError::ParserError(format!("{}", e)))?;
+        let doc = docs.drain(..).next().unwrap_or(Yaml::Null);
+        Ok(yaml_to_value(&doc))
+    }
+
+    fn emit(&self, value: &Value) -> Result<Vec<u8>, ConfigError> {
+        let yaml = value_to_yaml(value);
+        let mut out = String::new();
+        {
+            let mut emitter = YamlEmitter::new(&mut out);
+            emitter
+                .dump(&yaml)
+                .map_err(|e| ConfigError::WriteError(format!("{:?}", e)))?;
+        }
+        Ok(out.into_bytes())
+    }
+}
+
+/// TOML backend, for files ending in `.toml`.
+pub struct TomlFormat;
+
+impl Format for TomlFormat {
+    fn parse(&self, bytes: &[u8]) -> Result<Value, ConfigError> {
+        let text = String::from_utf8_lossy(bytes);
+        let value: toml::Value =
+            toml::from_str(&text).map_err(|e| ConfigError::ParserError(format!("{}", e)))?;
+        Ok(toml_to_value(&value))
+    }
+
+    fn emit(&self, value: &Value) -> Result<Vec<u8>, ConfigError> {
+        let toml_value = value_to_toml(value);
+        toml::to_string_pretty(&toml_value)
+            .map(|s| s.into_bytes())
+            .map_err(|e| ConfigError::WriteError(format!("{}", e)))
+    }
+}
+
+/// JSON backend, for files ending in `.json`.
+pub struct JsonFormat;
+
+impl Format for JsonFormat {
+    fn parse(&self, bytes: &[u8]) -> Result<Value, ConfigError> {
+        let value: serde_json::Value =
+            serde_json::from_slice(bytes).map_err(|e| ConfigError::ParserError(format!("{}", e)))?;
+        Ok(json_to_value(&value))
+    }
+
+    fn emit(&self, value: &Value) -> Result<Vec<u8>, ConfigError> {
+        let json_value = value_to_json(value);
+        serde_json::to_vec_pretty(&json_value).map_err(|e| ConfigError::WriteError(format!("{}", e)))
+    }
+}
+
+fn yaml_to_value(yaml: &Yaml) -> Value {
+    match *yaml {
+        Yaml::Null | Yaml::BadValue => Value::Null,
+        Yaml::Boolean(b) => Value::Bool(b),
+        Yaml::Integer(i) => Value::Integer(i),
+        Yaml::Real(ref s) => Value::Real(s.parse().unwrap_or(0.0)),
+        Yaml::String(ref s) => Value::String(s.clone()),
+        Yaml::Array(ref items) => Value::Array(items.iter().map(yaml_to_value).collect()),
+        Yaml::Hash(ref map) => Value::Map(
+            map.iter()
+                .map(|(k, v)| (k.as_str().unwrap_or("").to_string(), yaml_to_value(v)))
+                .collect(),
+        ),
+        Yaml::Alias(_) => Value::Null,
+    }
+}
+
+fn value_to_yaml(value: &Value) -> Yaml {
+    match *value {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(b),
+        Value::Integer(i) => Yaml::Integer(i),
+        Value::Real(f) => Yaml::Real(f.to_string()),
+        Value::String(ref s) => Yaml::String(s.clone()),
+        Value::Array(ref items) => Yaml::Array(items.iter().map(value_to_yaml).collect()),
+        Value::Map(ref entries) => {
+            let mut hash = yaml_rust::yaml::Hash::new();
+            for &(ref k, ref v) in entries {
+                hash.insert(Yaml::String(k.clone()), value_to_yaml(v));
+            }
+            Yaml::Hash(hash)
+        }
+    }
+}
+
+/// Converts a [`Value::Map`](enum.Value.html) to a `toml::Value::Table`, which is a `BTreeMap`
+/// under the hood. That means a round trip through `TomlFormat` sorts keys alphabetically instead
+/// of preserving the `Value`'s `Vec` order — and that loss already happens on the parse side
+/// (`toml_to_value`, fed from the same `BTreeMap`-backed `Table`), so fixing emission alone
+/// wouldn't make the format order-preserving anyway. Avoiding the `toml` crate's `Table`
+/// entirely and hand-rolling an order-preserving TOML emitter (and parser) is a much bigger
+/// undertaking than this one fix, given TOML's table-header/array-of-tables syntax, so for now
+/// this is a documented limitation (see [`Value::Map`](enum.Value.html#variant.Map)) rather than
+/// a silent one.
+fn toml_to_value(value: &toml::Value) -> Value {
+    match *value {
+        toml::Value::String(ref s) => Value::String(s.clone()),
+        toml::Value::Integer(i) => Value::Integer(i),
+        toml::Value::Float(f) => Value::Real(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(ref d) => Value::String(d.to_string()),
+        toml::Value::Array(ref items) => Value::Array(items.iter().map(toml_to_value).collect()),
+        toml::Value::Table(ref table) => {
+            Value::Map(table.iter().map(|(k, v)| (k.clone(), toml_to_value(v))).collect())
+        }
+    }
+}
+
+fn value_to_toml(value: &Value) -> toml::Value {
+    match *value {
+        Value::Null => toml::Value::String(String::new()),
+        Value::Bool(b) => toml::Value::Boolean(b),
+        Value::Integer(i) => toml::Value::Integer(i),
+        Value::Real(f) => toml::Value::Float(f),
+        Value::String(ref s) => toml::Value::String(s.clone()),
+        Value::Array(ref items) => toml::Value::Array(items.iter().map(value_to_toml).collect()),
+        Value::Map(ref entries) => {
+            let mut table = BTreeMap::new();
+            for &(ref k, ref v) in entries {
+                table.insert(k.clone(), value_to_toml(v));
+            }
+            toml::Value::Table(table.into_iter().collect())
+        }
+    }
+}
+
+/// Same limitation as [`toml_to_value`](fn.toml_to_value.html): `serde_json::Map` is a `BTreeMap`
+/// unless `serde_json`'s `preserve_order` feature is enabled, so `JsonFormat` also sorts keys
+/// alphabetically on both the parse and emit side rather than preserving the original file order.
+fn json_to_value(value: &serde_json::Value) -> Value {
+    match *value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Bool(b),
+        serde_json::Value::Number(ref n) => {
+            if let Some(i) = n.as_i64() {
+                Value::Integer(i)
+            } else {
+                Value::Real(n.as_f64().unwrap_or(0.0))
+            }
+        }
+        serde_json::Value::String(ref s) => Value::String(s.clone()),
+        serde_json::Value::Array(ref items) => Value::Array(items.iter().map(json_to_value).collect()),
+        serde_json::Value::Object(ref map) => {
+            Value::Map(map.iter().map(|(k, v)| (k.clone(), json_to_value(v))).collect())
+        }
+    }
+}
+
+fn value_to_json(value: &Value) -> serde_json::Value {
+    match *value {
+        Value::Null => serde_json::Value::Null,
+        Value::Bool(b) => serde_json::Value::Bool(b),
+        Value::Integer(i) => serde_json::Value::from(i),
+        Value::Real(f) => serde_json::Value::from(f),
+        Value::String(ref s) => serde_json::Value::String(s.clone()),
+        Value::Array(ref items) => serde_json::Value::Array(items.iter().map(value_to_json).collect()),
+        Value::Map(ref entries) => {
+            let mut map = serde_json::Map::new();
+            for &(ref k, ref v) in entries {
+                map.insert(k.clone(), value_to_json(v));
+            }
+            serde_json::Value::Object(map)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys_of(value: &Value) -> Vec<String> {
+        value
+            .as_map()
+            .unwrap()
+            .iter()
+            .map(|&(ref k, _)| k.clone())
+            .collect()
+    }
+
+    #[test]
+    fn yaml_round_trip_preserves_scalars_and_key_order() {
+        let format = format_for_extension("yml").unwrap();
+        let value = format
+            .parse(b"zebra: 1\napple: 2.5\nmango: \"three\"\n")
+            .unwrap();
+
+        let bytes = format.emit(&value).unwrap();
+        let reparsed = format.parse(&bytes).unwrap();
+
+        assert_eq!(keys_of(&reparsed), vec!["zebra", "apple", "mango"]);
+        let map = reparsed.as_map().unwrap();
+        assert_eq!(Value::map_get(map, "zebra"), Some(&Value::Integer(1)));
+        assert_eq!(Value::map_get(map, "apple"), Some(&Value::Real(2.5)));
+        assert_eq!(Value::map_get(map, "mango"), Some(&Value::String("three".to_string())));
+    }
+
+    #[test]
+    fn yaml_round_trip_preserves_nested_maps_and_arrays() {
+        let format = format_for_extension("yml").unwrap();
+        let value = format
+            .parse(b"title: demo\ndisplay:\n  brightness: 0.5\n  size: [1, 2]\n")
+            .unwrap();
+
+        let bytes = format.emit(&value).unwrap();
+        let reparsed = format.parse(&bytes).unwrap();
+
+        let display = Value::map_get(reparsed.as_map().unwrap(), "display").unwrap();
+        assert_eq!(
+            Value::map_get(display.as_map().unwrap(), "brightness"),
+            Some(&Value::Real(0.5))
+        );
+        let size = Value::map_get(display.as_map().unwrap(), "size").unwrap();
+        assert_eq!(size.as_array().unwrap(), &[Value::Integer(1), Value::Integer(2)]);
+    }
+
+    #[test]
+    fn toml_round_trip_preserves_scalars_but_sorts_keys() {
+        let format = format_for_extension("toml").unwrap();
+        let value = format.parse(b"zebra = 1\napple = 2\nmango = 3\n").unwrap();
+
+        let bytes = format.emit(&value).unwrap();
+        let reparsed = format.parse(&bytes).unwrap();
+
+        let map = reparsed.as_map().unwrap();
+        assert_eq!(Value::map_get(map, "zebra"), Some(&Value::Integer(1)));
+        assert_eq!(Value::map_get(map, "apple"), Some(&Value::Integer(2)));
+        assert_eq!(Value::map_get(map, "mango"), Some(&Value::Integer(3)));
+
+        // Unlike YamlFormat, the `toml` crate's `Table` is a `BTreeMap`, so a round trip through
+        // it sorts keys alphabetically rather than preserving the original file order (see the
+        // note on `toml_to_value`).
+        assert_eq!(keys_of(&reparsed), vec!["apple", "mango", "zebra"]);
+    }
+
+    #[test]
+    fn toml_round_trip_preserves_nested_tables_and_arrays() {
+        let format = format_for_extension("toml").unwrap();
+        let value = format
+            .parse(b"title = \"demo\"\n\n[display]\nbrightness = 0.5\nsize = [1, 2]\n")
+            .unwrap();
+
+        let bytes = format.emit(&value).unwrap();
+        let reparsed = format.parse(&bytes).unwrap();
+
+        let display = Value::map_get(reparsed.as_map().unwrap(), "display").unwrap();
+        assert_eq!(
+            Value::map_get(display.as_map().unwrap(), "brightness"),
+            Some(&Value::Real(0.5))
+        );
+        let size = Value::map_get(display.as_map().unwrap(), "size").unwrap();
+        assert_eq!(size.as_array().unwrap(), &[Value::Integer(1), Value::Integer(2)]);
+    }
+
+    #[test]
+    fn json_round_trip_preserves_scalars_but_sorts_keys() {
+        let format = format_for_extension("json").unwrap();
+        let value = format
+            .parse(br#"{"zebra": 1, "apple": 2, "mango": 3}"#)
+            .unwrap();
+
+        let bytes = format.emit(&value).unwrap();
+        let reparsed = format.parse(&bytes).unwrap();
+
+        let map = reparsed.as_map().unwrap();
+        assert_eq!(Value::map_get(map, "zebra"), Some(&Value::Integer(1)));
+        assert_eq!(Value::map_get(map, "apple"), Some(&Value::Integer(2)));
+        assert_eq!(Value::map_get(map, "mango"), Some(&Value::Integer(3)));
+
+        // Same limitation as TOML: `serde_json::Map` sorts without the `preserve_order` feature.
+        assert_eq!(keys_of(&reparsed), vec!["apple", "mango", "zebra"]);
+    }
+
+    #[test]
+    fn json_round_trip_preserves_nested_objects_and_arrays() {
+        let format = format_for_extension("json").unwrap();
+        let value = format
+            .parse(br#"{"title": "demo", "display": {"brightness": 0.5, "size": [1, 2]}}"#)
+            .unwrap();
+
+        let bytes = format.emit(&value).unwrap();
+        let reparsed = format.parse(&bytes).unwrap();
+
+        let display = Value::map_get(reparsed.as_map().unwrap(), "display").unwrap();
+        assert_eq!(
+            Value::map_get(display.as_map().unwrap(), "brightness"),
+            Some(&Value::Real(0.5))
+        );
+        let size = Value::map_get(display.as_map().unwrap(), "size").unwrap();
+        assert_eq!(size.as_array().unwrap(), &[Value::Integer(1), Value::Integer(2)]);
+    }
+}
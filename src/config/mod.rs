@@ -1,5 +1,5 @@
 
-//! Loading configuration (.yaml/.yml) files into a structure for easy usage
+//! Loading configuration (.yaml/.yml, .toml, or .json) files into a structure for easy usage
 //!
 //! # Basic usage:
 //! ```rust
@@ -28,9 +28,11 @@
 //! name: type = default,
 //! ```
 //!
-//! The field name will be looked up when attempting to load from a .yml/.yaml file. If it is
-//! found then the value will be converted from a yaml type to a rust type and assigned to the
-//! field.
+//! The field name will be looked up when attempting to load from a config file. Which
+//! [`Format`](trait.Format.html) is used to parse that file is chosen from its extension
+//! (`.yml`/`.yaml`, `.toml`, or `.json`); whichever one it is, the file is parsed into an
+//! intermediate [`Value`](enum.Value.html) tree before being converted to a rust type and
+//! assigned to the field.
 //!
 //! In the case that the value is either the wrong type from the field's or simply cannot be
 //! found in the file, the field will be defaulted to `default`.
@@ -55,8 +57,8 @@
 //! # fn main() { }
 //! ```
 //! 
-//! # External .yml/.yaml files
-//! In the event that a config is getting too long, you can define it in the .yml/.yaml file as
+//! # External config files
+//! In the event that a config is getting too long, you can define it in the config file as
 //! "extern"
 //!
 //! example: 
@@ -65,10 +67,57 @@
 //! display: "extern"
 //! ```
 //!
-//! This works similarly to rust's module system. It will first search for "\\display\\config.yml"
-//! in the current context. If it cannot find it, then it will look for "\\display.yml". If it
-//! cannot find either of these, then the value will be defaulted in addition to `display` being
-//! overwritten if you called `write_file()`.
+//! This works similarly to rust's module system. It will first search for "\\display\\config.*"
+//! (trying each known extension) in the current context. If it cannot find it, then it will look
+//! for "\\display.*". If it cannot find either of these, then the value will be defaulted in
+//! addition to `display` being overwritten if you called `write_file()`. The file found does not
+//! have to be the same format as the file that referenced it, so a YAML root config can pull in a
+//! TOML `display` section.
+//!
+//! # Environment variable overrides
+//! Loading through [`Element::from_file_with_env`](trait.Element.html#method.from_file_with_env)
+//! instead of `from_file` adds a pass on top of the file: every scalar leaf field can be
+//! overridden by an environment variable named after its dotted path, uppercased with `.`
+//! replaced by `_` and prefixed with whatever prefix you pass in. Array fields are not overridden. So `display.brightness` loaded with prefix
+//! `"amethyst"` is overridden by `AMETHYST_DISPLAY_BRIGHTNESS=0.8`. The precedence order is env var
+//! > file > compiled-in default.
+//!
+//! # Merging multiple sources
+//! [`Element::from_sources`](trait.Element.html#method.from_sources) takes an ordered list of
+//! paths (e.g. a packaged default, then a system-wide file, then a user file) and deep-merges
+//! their value trees before converting the result to `Self`, so a later source only has to
+//! mention the keys it wants to change; everything else falls through from the earlier sources
+//! and, ultimately, the compiled-in defaults.
+//!
+//! # Live reloading
+//! [`Element::watch`](trait.Element.html#method.watch) spawns a background thread that re-reads
+//! a value's backing file(s) (including any `"extern"` files it pulled in) whenever they change
+//! on disk, and publishes the fresh value through an `Arc<RwLock<Self>>`. A parse error during a
+//! reload keeps the last-good value and is reported through the callback you pass in, rather than
+//! tearing down the watch.
+//!
+//! # String interpolation
+//! A `${VAR}` placeholder inside a string field is expanded from the environment variable `VAR`
+//! at load time, so e.g. `file_path: "${XDG_STATE_HOME}/new_project.log"` resolves to wherever
+//! that variable points. A literal `$` is written as `$$`; an unresolved `${VAR}` is a recoverable
+//! `ConfigError` rather than text left dangling in the loaded struct. `write_file`/`to_string`
+//! re-escape any literal `$` so the result round-trips.
+//!
+//! # Embedded defaults
+//! `config!($name from_embedded "defaults/config.yml", { ... })` bakes that file's bytes into the
+//! binary with `include_bytes!`, in addition to the usual per-field `= default` values. If
+//! `from_file`'s path doesn't exist on disk, the embedded bytes are parsed through the normal
+//! `Element` path instead, the same way a packaged `application.yml` ships inside a jar and is
+//! overridden by an external file when one is present. This gives designers a real, diffable YAML
+//! document to read instead of only compiled-in rust defaults.
+//!
+//! # Typed path access
+//! [`Element::get`](trait.Element.html#method.get) and
+//! [`Element::set`](trait.Element.html#method.set) address a deeply nested field with a dotted/
+//! subscript path string, e.g. `"display.size[0]"` or `"inner.inner_inner.field"`, without having
+//! to statically name every intermediate struct. `get` returns the addressed
+//! [`Value`](enum.Value.html); `set` writes one back through the same path, which pairs naturally
+//! with `write_file` to persist a single edit. Handy for tooling and in-game consoles.
 //!
 //! # Enums
 //! While this is little more than just a more convenient conversion tool, `config_enum!`
@@ -99,10 +148,17 @@ pub use yaml_rust::Yaml;
 
 #[macro_use]
 mod definitions;
+mod format;
+mod path;
+mod value;
+mod watch;
 mod yaml;
 
 pub use config::yaml::{Element, to_string};
 pub use config::definitions::{ConfigMeta, ConfigError};
+pub use config::format::Format;
+pub use config::value::Value;
+pub use config::watch::WatchHandle;
 
 // Defines types along with defaulting values
 config_enum!(Test {
@@ -0,0 +1,99 @@
+//! Dotted/subscript path access into a [`Value`](enum.Value.html) tree, used by
+//! [`Element::get`](trait.Element.html#method.get) and
+//! [`Element::set`](trait.Element.html#method.set) to address deeply nested fields (e.g.
+//! `"display.size[0]"`, `"inner.inner_inner.field"`) without statically naming every intermediate
+//! struct.
+
+use config::definitions::ConfigError;
+use config::value::Value;
+
+/// One step of a parsed path: either a map key or an array subscript.
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parses a dotted/subscript path string into its segments. `.` separates map keys; `[n]`
+/// subscripts an array, and may be chained or follow a key directly (`"size[0]"`). Returns `None`
+/// for a malformed subscript (not a number, unclosed, or followed by other text).
+fn parse(path: &str) -> Option<Vec<Segment>> {
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        let mut rest = part;
+
+        match rest.find('[') {
+            Some(bracket) => {
+                if bracket > 0 {
+                    segments.push(Segment::Key(rest[..bracket].to_string()));
+                }
+                rest = &rest[bracket..];
+
+                while rest.starts_with('[') {
+                    let end = rest.find(']')?;
+                    segments.push(Segment::Index(rest[1..end].parse().ok()?));
+                    rest = &rest[end + 1..];
+                }
+
+                if !rest.is_empty() {
+                    return None;
+                }
+            }
+            None => segments.push(Segment::Key(rest.to_string())),
+        }
+    }
+
+    Some(segments)
+}
+
+/// Reads the value addressed by `path` out of `value`, or `None` if the path doesn't resolve.
+pub fn get(value: &Value, path: &str) -> Option<Value> {
+    let segments = parse(path)?;
+    walk(value, &segments)
+}
+
+fn walk(value: &Value, segments: &[Segment]) -> Option<Value> {
+    match segments.split_first() {
+        None => Some(value.clone()),
+        Some((Segment::Key(key), rest)) => {
+            let map = value.as_map()?;
+            walk(Value::map_get(map, key)?, rest)
+        }
+        Some((Segment::Index(index), rest)) => {
+            let items = value.as_array()?;
+            walk(items.get(*index)?, rest)
+        }
+    }
+}
+
+/// Writes `new_value` at the location addressed by `path` inside `value`, returning an error if
+/// the path doesn't resolve to an existing map key or array index.
+pub fn set(value: &mut Value, path: &str, new_value: Value) -> Result<(), ConfigError> {
+    let segments = parse(path).ok_or_else(|| ConfigError::PathError(path.to_string()))?;
+    write(value, &segments, new_value, path)
+}
+
+fn write(value: &mut Value, segments: &[Segment], new_value: Value, full_path: &str) -> Result<(), ConfigError> {
+    match segments.split_first() {
+        None => {
+            *value = new_value;
+            Ok(())
+        }
+        Some((Segment::Key(key), rest)) => match *value {
+            Value::Map(ref mut entries) => {
+                match entries.iter_mut().find(|&&mut (ref k, _)| k == key) {
+                    Some(&mut (_, ref mut child)) => write(child, rest, new_value, full_path),
+                    None => Err(ConfigError::PathError(full_path.to_string())),
+                }
+            }
+            _ => Err(ConfigError::PathError(full_path.to_string())),
+        },
+        Some((Segment::Index(index), rest)) => match *value {
+            Value::Array(ref mut items) => match items.get_mut(*index) {
+                Some(child) => write(child, rest, new_value, full_path),
+                None => Err(ConfigError::PathError(full_path.to_string())),
+            },
+            _ => Err(ConfigError::PathError(full_path.to_string())),
+        },
+    }
+}
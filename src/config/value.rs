@@ -0,0 +1,112 @@
+//! A format-agnostic intermediate value tree.
+//!
+//! Every [`Format`](trait.Format.html) parses bytes into a `Value` and emits a `Value` back out
+//! to bytes, so the rest of the config system (the `Element` trait, the `config!` macro, merging,
+//! path access, ...) never has to know whether the original file was YAML, TOML, or JSON.
+
+/// A single node in a format-agnostic config value tree.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// An absent or explicit null value.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A signed integer.
+    Integer(i64),
+    /// A floating point number.
+    Real(f64),
+    /// A UTF-8 string.
+    String(String),
+    /// An ordered sequence of values.
+    Array(Vec<Value>),
+    /// An ordered mapping of string keys to values. Kept as a `Vec` rather than a `HashMap` so
+    /// that re-emitting a `Value` *can* preserve the original key order — `YamlFormat` does,
+    /// since `yaml_rust`'s `Hash` is itself insertion-ordered. `TomlFormat` and `JsonFormat`
+    /// don't: both parse into a `BTreeMap`-backed type (the `toml` crate's `Table`, and
+    /// `serde_json`'s `Map` without its `preserve_order` feature), which sorts keys
+    /// alphabetically before a `Value` tree is ever built, so there's no original order left to
+    /// preserve by the time the `Value` is emitted back out. See the comments above
+    /// `toml_to_value`/`json_to_value` in `config::format` for why this isn't patched over by
+    /// hand.
+    Map(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns the entries of this value as a map, if it is one.
+    pub fn as_map(&self) -> Option<&[(String, Value)]> {
+        match *self {
+            Value::Map(ref entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` in `map`, as produced by [`as_map`](#method.as_map).
+    pub fn map_get<'a>(map: &'a [(String, Value)], key: &str) -> Option<&'a Value> {
+        map.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v)
+    }
+
+    /// Returns the elements of this value as a slice, if it is an array.
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Array(ref items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns this value as a string slice, if it is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns this value as an `i64`, if it is an integer.
+    pub fn as_integer(&self) -> Option<i64> {
+        match *self {
+            Value::Integer(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Returns this value as an `f64`, if it is a real or an integer.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Real(f) => Some(f),
+            Value::Integer(i) => Some(i as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns this value as a `bool`, if it is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Deep-merges `overlay` onto `self`, returning the result. Where both sides are maps, keys
+    /// are merged recursively: a key only `self` defines is kept, a key only `overlay` defines is
+    /// added, and a key both define is merged (if both sides are themselves maps) or replaced by
+    /// `overlay`'s value otherwise. This is what lets a user file override just the keys it
+    /// mentions in a packaged default, rather than replacing whole sections wholesale.
+    pub fn merge(&self, overlay: &Value) -> Value {
+        match (self, overlay) {
+            (&Value::Map(ref base), &Value::Map(ref overlay)) => {
+                let mut merged: Vec<(String, Value)> = base.clone();
+                for &(ref key, ref overlay_value) in overlay {
+                    if let Some(&mut (_, ref mut existing)) =
+                        merged.iter_mut().find(|&&mut (ref k, _)| k == key)
+                    {
+                        *existing = existing.merge(overlay_value);
+                    } else {
+                        merged.push((key.clone(), overlay_value.clone()));
+                    }
+                }
+                Value::Map(merged)
+            }
+            (_, overlay) => overlay.clone(),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Live-reloading a `Config` from disk.
+//!
+//! [`Element::watch`](trait.Element.html#method.watch) spawns a background thread that re-reads a
+//! value's backing file(s) whenever they change, so a running game can pick up a tweak to e.g.
+//! `display.brightness` without a restart.
+
+extern crate notify;
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use self::notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use config::definitions::ConfigError;
+use config::yaml::Element;
+
+/// How often the watcher thread polls for a stop signal between filesystem events.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Handle to the background thread started by [`Element::watch`](trait.Element.html#method.watch).
+/// Call [`stop`](#method.stop) to shut the watcher down; the `Arc<RwLock<T>>` handed back
+/// alongside this handle remains valid (it just stops receiving updates).
+pub struct WatchHandle {
+    stop: Sender<()>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    /// Signals the watcher thread to exit and waits for it to do so.
+    pub fn stop(mut self) {
+        let _ = self.stop.send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Starts watching `path`, and every `"extern"` file it pulled in, for changes. On each change,
+/// `T::from_file(path)` is run again and the result published through the returned
+/// `Arc<RwLock<T>>`; on a parse error, the last-good value is kept and the error is handed to
+/// `on_error` instead.
+pub fn watch<T, F>(path: &Path, on_error: F) -> Result<(Arc<RwLock<T>>, WatchHandle), ConfigError>
+where
+    T: Element + Send + Sync + 'static,
+    F: Fn(ConfigError) + Send + 'static,
+{
+    let initial = T::from_file(path)?;
+    let meta = initial.config_meta();
+
+    let mut watched_paths: Vec<PathBuf> = vec![path.to_path_buf()];
+    watched_paths.extend(meta.extern_paths.iter().cloned());
+
+    let (event_tx, event_rx) = channel();
+    let mut watcher: RecommendedWatcher = Watcher::new(event_tx, POLL_INTERVAL)
+        .map_err(|e| ConfigError::WriteError(format!("{}", e)))?;
+    for watched_path in &watched_paths {
+        watcher
+            .watch(watched_path, RecursiveMode::NonRecursive)
+            .map_err(|e| ConfigError::WriteError(format!("{}", e)))?;
+    }
+
+    let value = Arc::new(RwLock::new(initial));
+    let (stop_tx, stop_rx) = channel();
+    let path = path.to_path_buf();
+    let thread_value = value.clone();
+
+    let thread = thread::spawn(move || {
+        // Keep the watcher alive for as long as the thread runs; dropping it stops events.
+        let _watcher = watcher;
+        loop {
+            if stop_rx.try_recv().is_ok() {
+                break;
+            }
+
+            match event_rx.recv_timeout(POLL_INTERVAL) {
+                Ok(_event) => match T::from_file(&path) {
+                    Ok(fresh) => {
+                        if let Ok(mut guard) = thread_value.write() {
+                            *guard = fresh;
+                        }
+                    }
+                    Err(err) => on_error(err),
+                },
+                Err(_) => continue,
+            }
+        }
+    });
+
+    Ok((
+        value,
+        WatchHandle {
+            stop: stop_tx,
+            thread: Some(thread),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    config!(WatchTestConfig {
+        value: i64 = 0,
+    });
+
+    fn unique_temp_path(name: &str) -> PathBuf {
+        let nanos = ::std::time::SystemTime::now()
+            .duration_since(::std::time::UNIX_EPOCH)
+            .unwrap()
+            .subsec_nanos();
+        ::std::env::temp_dir().join(format!("amethyst_watch_test_{}_{}", nanos, name))
+    }
+
+    /// Polls `check` for up to a few seconds, since the watcher reacts to a real filesystem event
+    /// on its own background thread rather than anything we can step deterministically.
+    fn wait_until<F: Fn() -> bool>(check: F) -> bool {
+        for _ in 0..40 {
+            if check() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        check()
+    }
+
+    #[test]
+    fn watch_picks_up_a_change_to_the_backing_file() {
+        let path = unique_temp_path("watch_change.yml");
+        fs::write(&path, "value: 1\n").unwrap();
+
+        let (current, handle) = WatchTestConfig::watch(&path, |_| {}).unwrap();
+        assert_eq!(current.read().unwrap().value, 1);
+
+        fs::write(&path, "value: 2\n").unwrap();
+        let updated = wait_until(|| current.read().unwrap().value == 2);
+
+        handle.stop();
+        let _ = fs::remove_file(&path);
+
+        assert!(updated, "watcher never picked up the file change");
+    }
+
+    #[test]
+    fn watch_keeps_the_last_good_value_and_reports_parse_errors() {
+        let path = unique_temp_path("watch_bad_parse.yml");
+        fs::write(&path, "value: 1\n").unwrap();
+
+        let errors = Arc::new(RwLock::new(Vec::new()));
+        let errors_thread = errors.clone();
+        let (current, handle) = WatchTestConfig::watch(&path, move |err| {
+            errors_thread.write().unwrap().push(format!("{}", err));
+        }).unwrap();
+
+        // Unterminated flow sequence: not valid YAML, so from_file should fail to parse it.
+        fs::write(&path, "value: [1, 2\n").unwrap();
+        let errored = wait_until(|| !errors.read().unwrap().is_empty());
+        let value_after_bad_write = current.read().unwrap().value;
+
+        handle.stop();
+        let _ = fs::remove_file(&path);
+
+        assert!(errored, "on_error was never called for the bad parse");
+        assert_eq!(value_after_bad_write, 1);
+    }
+}
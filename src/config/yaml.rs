@@ -0,0 +1,697 @@
+//! The `Element` trait: conversion between a `config!`-generated struct (or a primitive type)
+//! and the format-agnostic [`Value`](enum.Value.html) tree, plus loading/writing files through
+//! whichever [`Format`](trait.Format.html) matches the path's extension.
+
+use std::fs;
+use std::path::Path;
+
+use config::definitions::{ConfigError, ConfigMeta};
+use config::format::{format_for_extension, Format, EXTENSIONS};
+use config::value::Value;
+
+/// Implemented by anything that can be converted to and from a [`Value`](enum.Value.html) tree,
+/// and therefore loaded from or written to a config file. Implemented automatically for structs
+/// and enums declared with `config!`/`config_enum!`, and provided here for the primitive types
+/// they're built out of.
+pub trait Element: Sized {
+    /// Builds `Self` out of a value tree, defaulting any field that is missing or of the wrong
+    /// type. `meta` is attached to the result as-is; only `config!`-generated structs make use of
+    /// it.
+    fn from_value(value: &Value, meta: ConfigMeta) -> Self;
+
+    /// Converts `self` into a value tree.
+    fn to_value(&self) -> Value;
+
+    /// Returns a copy of this value's `ConfigMeta`, used by `config::watch` to find every file an
+    /// `Element` was loaded from. Primitives have no meta of their own; only `config!`-generated
+    /// structs override this.
+    fn config_meta(&self) -> ConfigMeta {
+        ConfigMeta::default()
+    }
+
+    /// Reads the field addressed by a dotted/subscript `path` (e.g. `"display.size[0]"`,
+    /// `"inner.inner_inner.field"`) out of this value, or `None` if the path doesn't resolve. See
+    /// [`config::path`](path/index.html).
+    ///
+    /// There is deliberately no `get_mut`: the `Value` this returns is a disconnected snapshot
+    /// produced by `to_value()`, not a live view into `self`, so a `&mut Value` handed back from
+    /// it wouldn't write anywhere when mutated — every field would need to round-trip through
+    /// `from_value` again regardless. Use [`set`](#method.set) to write a new value back through
+    /// the same path instead.
+    fn get(&self, path: &str) -> Option<Value> {
+        ::config::path::get(&self.to_value(), path)
+    }
+
+    /// Writes `new_value` at the field addressed by a dotted/subscript `path`, then rebuilds
+    /// `Self` from the result. Errors if the path doesn't resolve to an existing field.
+    fn set(&mut self, path: &str, new_value: Value) -> Result<(), ConfigError> {
+        let mut tree = self.to_value();
+        ::config::path::set(&mut tree, path, new_value)?;
+        *self = Self::from_value(&tree, self.config_meta());
+        Ok(())
+    }
+
+    /// Deep-merges `overlay` onto `self`, returning the result (see
+    /// [`Value::merge`](enum.Value.html#method.merge)). Since both sides are already fully
+    /// populated structs, every field in `overlay` is considered "present" and wins; to merge
+    /// partial files where an absent key should fall through, use
+    /// [`from_sources`](#method.from_sources) instead, which merges before defaulting.
+    fn merge(&self, overlay: &Self) -> Self {
+        let merged = self.to_value().merge(&overlay.to_value());
+        Self::from_value(&merged, ConfigMeta::default())
+    }
+
+    /// The path and bytes of a config file baked into the binary with `include_bytes!` via
+    /// `config!($name from_embedded "path", { ... })`, used by `from_file` as a fallback when the
+    /// requested on-disk file doesn't exist. `None` unless the macro was invoked that way.
+    fn embedded_default() -> Option<(&'static str, &'static [u8])> {
+        None
+    }
+
+    /// Loads `Self` from a file, picking the [`Format`](trait.Format.html) by the path's
+    /// extension, and resolving any `"extern"` fields relative to the file's directory. If the
+    /// path doesn't exist and `Self` has an [`embedded_default`](#method.embedded_default), that
+    /// is parsed instead.
+    fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+
+        if !path.is_file() {
+            if let Some((embedded_path, bytes)) = Self::embedded_default() {
+                let format = format_for_path(Path::new(embedded_path))?;
+                let mut value = format.parse(bytes)?;
+                interpolate_strings(&mut value)?;
+                let mut meta = ConfigMeta::default();
+                meta.path = Some(path.to_path_buf());
+                return Ok(Self::from_value(&value, meta));
+            }
+        }
+
+        let (value, meta) = load_value(path)?;
+        Ok(Self::from_value(&value, meta))
+    }
+
+    /// Like [`from_file`](#method.from_file), but afterwards every scalar leaf field may
+    /// additionally be overridden by an environment variable named `PREFIX_SECTION_FIELD` (the
+    /// field's dotted path, uppercased, with `.` replaced by `_`). Array fields are not
+    /// overridden. This gives a precedence order of env var >
+    /// file > compiled-in default: the override pass runs on `Self::to_value()` of the already
+    /// file-loaded (and defaulted) struct, rather than on the raw file contents, so a field that
+    /// was absent from the file — and therefore already defaulted — is still a candidate for the
+    /// env var to win over.
+    fn from_file_with_env<P: AsRef<Path>>(path: P, prefix: &str) -> Result<Self, ConfigError> {
+        let loaded = Self::from_file(path)?;
+        let mut normalized = loaded.to_value();
+        apply_env_overrides(&mut normalized, prefix, &mut Vec::new());
+        let mut meta = loaded.config_meta();
+        meta.env_prefix = Some(prefix.to_string());
+        Ok(Self::from_value(&normalized, meta))
+    }
+
+    /// Loads `Self` from an ordered list of sources, deep-merging them into a single value tree
+    /// before converting it to `Self` (see [`Value::merge`](enum.Value.html#method.merge)): a
+    /// later source overrides only the keys it defines, and nested sections merge recursively
+    /// rather than being wholly replaced. Typical use is a packaged default, then a system-wide
+    /// file, then a user file, each filling in only what it wants to change.
+    fn from_sources<P: AsRef<Path>>(paths: &[P]) -> Result<Self, ConfigError> {
+        let mut merged = Value::Map(Vec::new());
+        let mut source_paths = Vec::new();
+
+        for path in paths {
+            let (value, _) = load_value(path.as_ref())?;
+            merged = merged.merge(&value);
+            source_paths.push(path.as_ref().to_path_buf());
+        }
+
+        let mut meta = ConfigMeta::default();
+        meta.source_paths = source_paths;
+        Ok(Self::from_value(&merged, meta))
+    }
+
+    /// Watches `path`, and every `"extern"` file it pulls in, for changes, re-loading `Self` and
+    /// publishing it through the returned `Arc<RwLock<Self>>` whenever they do. See
+    /// [`config::watch`](watch/index.html) for details.
+    fn watch<P, F>(path: P, on_error: F) -> Result<(::std::sync::Arc<::std::sync::RwLock<Self>>, ::config::watch::WatchHandle), ConfigError>
+    where
+        Self: Send + Sync + 'static,
+        P: AsRef<Path>,
+        F: Fn(ConfigError) + Send + 'static,
+    {
+        ::config::watch::watch(path.as_ref(), on_error)
+    }
+
+    /// Writes `self` to a file, picking the [`Format`](trait.Format.html) by the path's
+    /// extension.
+    fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let format = format_for_path(path)?;
+        let mut value = self.to_value();
+        escape_strings(&mut value);
+        let bytes = format.emit(&value)?;
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+}
+
+/// Converts a value tree to a `String`, using the YAML format. Handy for debugging or for writing
+/// a value out somewhere other than a file.
+pub fn to_string(value: &Value) -> Result<String, ConfigError> {
+    let format = format_for_extension("yml").expect("yml is always a known extension");
+    let mut value = value.clone();
+    escape_strings(&mut value);
+    let bytes = format.emit(&value)?;
+    String::from_utf8(bytes).map_err(|e| ConfigError::WriteError(format!("{}", e)))
+}
+
+/// Parses `path` through the `Format` matching its extension and resolves any `"extern"` fields,
+/// shared by `from_file` and `from_sources`.
+fn load_value(path: &Path) -> Result<(Value, ConfigMeta), ConfigError> {
+    let format = format_for_path(path)?;
+    let bytes = fs::read(path)?;
+    let mut value = format.parse(&bytes)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut meta = ConfigMeta::default();
+    meta.path = Some(path.to_path_buf());
+    resolve_externs(&mut value, base_dir, &mut meta)?;
+    interpolate_strings(&mut value)?;
+    Ok((value, meta))
+}
+
+/// Walks `value`, expanding `${VAR}` placeholders in every string leaf from environment
+/// variables. A literal `$` is written as `$$` (see [`escape`](fn.escape.html)); a `${NAME}` whose
+/// `NAME` isn't set as an env var is a recoverable [`ConfigError`](enum.ConfigError.html) rather
+/// than text left dangling in the config.
+fn interpolate_strings(value: &mut Value) -> Result<(), ConfigError> {
+    match *value {
+        Value::String(ref mut s) => {
+            *s = expand(s)?;
+        }
+        Value::Map(ref mut entries) => {
+            for &mut (_, ref mut child) in entries.iter_mut() {
+                interpolate_strings(child)?;
+            }
+        }
+        Value::Array(ref mut items) => {
+            for item in items.iter_mut() {
+                interpolate_strings(item)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Expands `${NAME}` placeholders in `raw` from environment variables, and un-escapes `$$` into a
+/// literal `$`.
+fn expand(raw: &str) -> Result<String, ConfigError> {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.peek().cloned() {
+            Some('$') => {
+                chars.next();
+                result.push('$');
+            }
+            Some('{') => {
+                chars.next();
+                let mut name = String::new();
+                let mut closed = false;
+                while let Some(c) = chars.next() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+
+                if !closed {
+                    return Err(ConfigError::InterpolationError(name));
+                }
+
+                match ::std::env::var(&name) {
+                    Ok(resolved) => result.push_str(&resolved),
+                    Err(_) => return Err(ConfigError::InterpolationError(name)),
+                }
+            }
+            _ => result.push('$'),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Inverse of [`expand`](fn.expand.html)'s escaping: turns every literal `$` into `$$`, so a
+/// string that went through interpolation round-trips cleanly through `write_file`/`to_string`
+/// instead of being re-interpreted as a new placeholder.
+fn escape(raw: &str) -> String {
+    raw.replace('$', "$$")
+}
+
+/// Walks `value`, escaping every string leaf with [`escape`](fn.escape.html). This is the inverse
+/// of `interpolate_strings`, and is applied only at the `write_file`/`to_string` boundary (i.e.
+/// when a `Value` is about to become file bytes) — never inside `Element::to_value`, so that a
+/// plain `to_value()`/`from_value()` round trip (as used by `get`, `set`, and `merge`) leaves
+/// string fields untouched instead of compounding escapes.
+fn escape_strings(value: &mut Value) {
+    match *value {
+        Value::String(ref mut s) => {
+            *s = escape(s);
+        }
+        Value::Map(ref mut entries) => {
+            for &mut (_, ref mut child) in entries.iter_mut() {
+                escape_strings(child);
+            }
+        }
+        Value::Array(ref mut items) => {
+            for item in items.iter_mut() {
+                escape_strings(item);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Walks `value`, overriding each scalar leaf (bool/integer/real/string, not a nested map or
+/// array) with the environment variable `PREFIX_SECTION_FIELD...` if it is set, where the suffix
+/// is `path` (the field's dotted path through the map, e.g. `["display", "brightness"]`)
+/// uppercased and joined with `_`. Arrays aren't addressable by a single scalar env var, so they
+/// are left alone entirely rather than risk an unrelated env var resetting them.
+fn apply_env_overrides(value: &mut Value, prefix: &str, path: &mut Vec<String>) {
+    if let Value::Map(ref mut entries) = *value {
+        for &mut (ref key, ref mut child) in entries.iter_mut() {
+            path.push(key.clone());
+            match *child {
+                Value::Map(_) => apply_env_overrides(child, prefix, path),
+                Value::Array(_) => {}
+                _ => {
+                    let var_name = format!("{}_{}", prefix, path.join("_")).to_uppercase();
+                    if let Ok(raw) = ::std::env::var(&var_name) {
+                        *child = parse_like(child, &raw);
+                    }
+                }
+            }
+            path.pop();
+        }
+    }
+}
+
+/// Parses `raw` into the same `Value` variant as `existing`, so the override goes through the
+/// same conversion path a value loaded straight from the file would.
+fn parse_like(existing: &Value, raw: &str) -> Value {
+    match *existing {
+        Value::Bool(_) => raw.parse().map(Value::Bool).unwrap_or_else(|_| Value::String(raw.to_string())),
+        Value::Integer(_) => raw.parse().map(Value::Integer).unwrap_or_else(|_| Value::String(raw.to_string())),
+        Value::Real(_) => raw.parse().map(Value::Real).unwrap_or_else(|_| Value::String(raw.to_string())),
+        _ => Value::String(raw.to_string()),
+    }
+}
+
+fn format_for_path(path: &Path) -> Result<Box<Format>, ConfigError> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(format_for_extension)
+        .ok_or(ConfigError::ExtensionError)
+}
+
+/// Walks `value` looking for `"extern"` string markers and replaces each with the contents of the
+/// sibling file it names, recursively resolving that file's own `"extern"` fields relative to its
+/// directory. Tries `<base_dir>/<field>/config.<ext>` before `<base_dir>/<field>.<ext>`, for each
+/// known extension, and leaves the field defaulted (by doing nothing) if neither is found.
+///
+/// Every file pulled in this way, at any depth, is appended to `meta.extern_paths` flat (not
+/// keyed by field name, since two different sections may use the same field name for an extern),
+/// so a consumer like `config::watch` can find every file the load touched.
+fn resolve_externs(value: &mut Value, base_dir: &Path, meta: &mut ConfigMeta) -> Result<(), ConfigError> {
+    if let Value::Map(ref mut entries) = *value {
+        for &mut (ref key, ref mut child) in entries.iter_mut() {
+            let is_extern = match *child {
+                Value::String(ref s) => s == "extern",
+                _ => false,
+            };
+
+            if is_extern {
+                if let Some((found_path, mut loaded)) = load_extern(base_dir, key)? {
+                    let child_dir = found_path.parent().unwrap_or(base_dir).to_path_buf();
+                    let mut child_meta = ConfigMeta::default();
+                    resolve_externs(&mut loaded, &child_dir, &mut child_meta)?;
+                    meta.extern_paths.push(found_path);
+                    meta.extern_paths.extend(child_meta.extern_paths);
+                    *child = loaded;
+                }
+            } else {
+                resolve_externs(child, base_dir, meta)?;
+            }
+        }
+    } else if let Value::Array(ref mut items) = *value {
+        for item in items.iter_mut() {
+            resolve_externs(item, base_dir, meta)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn load_extern(base_dir: &Path, field: &str) -> Result<Option<(::std::path::PathBuf, Value)>, ConfigError> {
+    for ext in EXTENSIONS {
+        let nested = base_dir.join(field).join(format!("config.{}", ext));
+        if nested.is_file() {
+            let format = format_for_extension(ext).expect("extension came from EXTENSIONS");
+            let bytes = fs::read(&nested)?;
+            return Ok(Some((nested, format.parse(&bytes)?)));
+        }
+    }
+
+    for ext in EXTENSIONS {
+        let sibling = base_dir.join(format!("{}.{}", field, ext));
+        if sibling.is_file() {
+            let format = format_for_extension(ext).expect("extension came from EXTENSIONS");
+            let bytes = fs::read(&sibling)?;
+            return Ok(Some((sibling, format.parse(&bytes)?)));
+        }
+    }
+
+    Ok(None)
+}
+
+macro_rules! impl_element_for_primitive {
+    ($ty:ty, $variant:ident, $as_fn:ident) => {
+        impl Element for $ty {
+            fn from_value(value: &Value, _meta: ConfigMeta) -> Self {
+                value.$as_fn().map(|v| v as $ty).unwrap_or_default()
+            }
+
+            fn to_value(&self) -> Value {
+                Value::$variant(*self as _)
+            }
+        }
+    };
+}
+
+impl_element_for_primitive!(bool, Bool, as_bool);
+impl_element_for_primitive!(i8, Integer, as_integer);
+impl_element_for_primitive!(i16, Integer, as_integer);
+impl_element_for_primitive!(i32, Integer, as_integer);
+impl_element_for_primitive!(i64, Integer, as_integer);
+impl_element_for_primitive!(u8, Integer, as_integer);
+impl_element_for_primitive!(u16, Integer, as_integer);
+impl_element_for_primitive!(u32, Integer, as_integer);
+impl_element_for_primitive!(u64, Integer, as_integer);
+impl_element_for_primitive!(f32, Real, as_f64);
+impl_element_for_primitive!(f64, Real, as_f64);
+
+impl Element for String {
+    fn from_value(value: &Value, _meta: ConfigMeta) -> Self {
+        value.as_str().map(|s| s.to_string()).unwrap_or_default()
+    }
+
+    fn to_value(&self) -> Value {
+        // No escaping here: `to_value`/`from_value` must stay symmetric so that `get`, `set`, and
+        // `merge` (which round-trip through them without ever touching a file) don't mangle `$`.
+        // Escaping for storage happens at the `write_file`/`to_string` boundary instead, see
+        // `escape_strings`.
+        Value::String(self.clone())
+    }
+}
+
+impl<T: Element> Element for Vec<T> {
+    fn from_value(value: &Value, _meta: ConfigMeta) -> Self {
+        value
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| T::from_value(item, ConfigMeta::default()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Array(self.iter().map(Element::to_value).collect())
+    }
+}
+
+macro_rules! impl_element_for_array {
+    ($n:expr) => {
+        impl<T: Element + Default + Copy> Element for [T; $n] {
+            fn from_value(value: &Value, _meta: ConfigMeta) -> Self {
+                let mut result = [T::default(); $n];
+                if let Some(items) = value.as_array() {
+                    for (slot, item) in result.iter_mut().zip(items.iter()) {
+                        *slot = T::from_value(item, ConfigMeta::default());
+                    }
+                }
+                result
+            }
+
+            fn to_value(&self) -> Value {
+                Value::Array(self.iter().map(Element::to_value).collect())
+            }
+        }
+    };
+}
+
+impl_element_for_array!(1);
+impl_element_for_array!(2);
+impl_element_for_array!(3);
+impl_element_for_array!(4);
+impl_element_for_array!(5);
+impl_element_for_array!(6);
+impl_element_for_array!(7);
+impl_element_for_array!(8);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    config!(DollarFieldConfig {
+        label: String = "default".to_string(),
+        note: String = "cost $5".to_string(),
+    });
+
+    #[test]
+    fn get_returns_the_unescaped_current_value() {
+        let config = DollarFieldConfig::default();
+        assert_eq!(config.get("note"), Some(Value::String("cost $5".to_string())));
+    }
+
+    #[test]
+    fn set_does_not_escape_unrelated_dollar_bearing_fields() {
+        let mut config = DollarFieldConfig::default();
+        config.set("label", Value::String("changed".to_string())).unwrap();
+        assert_eq!(config.label, "changed");
+        assert_eq!(config.note, "cost $5");
+    }
+
+    #[test]
+    fn repeated_set_calls_do_not_compound_escaping() {
+        let mut config = DollarFieldConfig::default();
+        for _ in 0..3 {
+            config.set("label", Value::String("changed".to_string())).unwrap();
+        }
+        assert_eq!(config.note, "cost $5");
+    }
+
+    config!(EnvOverrideConfig {
+        brightness: f64 = 1.0,
+        fullscreen: bool = false,
+        size: [u16; 2] = [9, 9],
+    });
+
+    fn unique_temp_path(name: &str) -> ::std::path::PathBuf {
+        let nanos = ::std::time::SystemTime::now()
+            .duration_since(::std::time::UNIX_EPOCH)
+            .unwrap()
+            .subsec_nanos();
+        ::std::env::temp_dir().join(format!("amethyst_config_test_{}_{}", nanos, name))
+    }
+
+    #[test]
+    fn env_override_applies_even_when_field_is_absent_from_file() {
+        let path = unique_temp_path("env_override_absent.yml");
+        fs::write(&path, "fullscreen: true\n").unwrap();
+
+        ::std::env::set_var("AMETHYSTTEST_BRIGHTNESS", "0.25");
+        let config = EnvOverrideConfig::from_file_with_env(&path, "AMETHYSTTEST").unwrap();
+        ::std::env::remove_var("AMETHYSTTEST_BRIGHTNESS");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(config.brightness, 0.25);
+        assert_eq!(config.fullscreen, true);
+    }
+
+    #[test]
+    fn env_override_does_not_corrupt_array_fields() {
+        let path = unique_temp_path("env_override_array.yml");
+        fs::write(&path, "size: [3, 4]\n").unwrap();
+
+        ::std::env::set_var("AMETHYSTTEST_SIZE", "not-an-array");
+        let config = EnvOverrideConfig::from_file_with_env(&path, "AMETHYSTTEST").unwrap();
+        ::std::env::remove_var("AMETHYSTTEST_SIZE");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(config.size, [3, 4]);
+    }
+
+    #[test]
+    fn merge_preserves_dollar_signs_in_untouched_string_fields() {
+        let mut base = DollarFieldConfig::default();
+        base.label = "base".to_string();
+        let mut overlay = DollarFieldConfig::default();
+        overlay.label = "overlay".to_string();
+
+        let merged = base.merge(&overlay);
+
+        assert_eq!(merged.label, "overlay");
+        assert_eq!(merged.note, "cost $5");
+    }
+
+    config!(PartialSourceInner {
+        a: i64 = 0,
+        b: i64 = 0,
+    });
+
+    config!(PartialSourceConfig {
+        title: String = "untitled".to_string(),
+        inner: PartialSourceInner = PartialSourceInner::default(),
+    });
+
+    #[test]
+    fn from_sources_deep_merges_partial_files_before_defaulting() {
+        let base_path = unique_temp_path("from_sources_base.yml");
+        fs::write(&base_path, "title: \"base title\"\ninner:\n  a: 1\n  b: 2\n").unwrap();
+
+        let override_path = unique_temp_path("from_sources_override.yml");
+        fs::write(&override_path, "inner:\n  b: 20\n").unwrap();
+
+        let config =
+            PartialSourceConfig::from_sources(&[base_path.clone(), override_path.clone()]).unwrap();
+
+        let _ = fs::remove_file(&base_path);
+        let _ = fs::remove_file(&override_path);
+
+        // title only appears in the base file, and falls through untouched.
+        assert_eq!(config.title, "base title");
+        // inner.a is only in the base file too.
+        assert_eq!(config.inner.a, 1);
+        // inner.b is in both; the later source wins without clobbering its sibling inner.a.
+        assert_eq!(config.inner.b, 20);
+        assert_eq!(config.config_meta().source_paths, vec![base_path, override_path]);
+    }
+
+    #[test]
+    fn string_to_value_and_from_value_are_symmetric_for_dollar_signs() {
+        let original = "cost $5.log".to_string();
+        let value = Element::to_value(&original);
+        assert_eq!(value, Value::String("cost $5.log".to_string()));
+        let restored: String = Element::from_value(&value, ConfigMeta::default());
+        assert_eq!(restored, original);
+    }
+
+    #[test]
+    fn escape_strings_is_reversed_by_interpolate_strings() {
+        let mut value = Value::String("cost $5.log".to_string());
+        escape_strings(&mut value);
+        assert_eq!(value, Value::String("cost $$5.log".to_string()));
+        interpolate_strings(&mut value).unwrap();
+        assert_eq!(value, Value::String("cost $5.log".to_string()));
+    }
+
+    #[test]
+    fn interpolate_strings_resolves_a_real_environment_variable() {
+        ::std::env::set_var("AMETHYSTTEST_INTERP_VALUE", "resolved");
+        let mut value = Value::String("prefix-${AMETHYSTTEST_INTERP_VALUE}-suffix".to_string());
+
+        interpolate_strings(&mut value).unwrap();
+        ::std::env::remove_var("AMETHYSTTEST_INTERP_VALUE");
+
+        assert_eq!(value, Value::String("prefix-resolved-suffix".to_string()));
+    }
+
+    #[test]
+    fn interpolate_strings_errors_on_an_unset_environment_variable() {
+        ::std::env::remove_var("AMETHYSTTEST_DOES_NOT_EXIST");
+        let mut value = Value::String("${AMETHYSTTEST_DOES_NOT_EXIST}".to_string());
+
+        match interpolate_strings(&mut value) {
+            Err(ConfigError::InterpolationError(ref name)) => {
+                assert_eq!(name, "AMETHYSTTEST_DOES_NOT_EXIST");
+            }
+            other => panic!("expected InterpolationError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn interpolate_strings_errors_on_an_unterminated_placeholder() {
+        let mut value = Value::String("${UNCLOSED".to_string());
+
+        match interpolate_strings(&mut value) {
+            Err(ConfigError::InterpolationError(ref name)) => assert_eq!(name, "UNCLOSED"),
+            other => panic!("expected InterpolationError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resolve_externs_flattens_paths_through_nested_extern_files() {
+        let root_dir = unique_temp_path("extern_chain_root");
+        fs::create_dir_all(root_dir.join("display").join("inner")).unwrap();
+
+        let root_path = root_dir.join("config.yml");
+        fs::write(&root_path, "display: extern\n").unwrap();
+
+        let display_path = root_dir.join("display").join("config.yml");
+        fs::write(&display_path, "inner: extern\n").unwrap();
+
+        let inner_path = root_dir.join("display").join("inner").join("config.yml");
+        fs::write(&inner_path, "field: 1\n").unwrap();
+
+        let (_, meta) = load_value(&root_path).unwrap();
+        let _ = fs::remove_dir_all(&root_dir);
+
+        assert_eq!(meta.extern_paths.len(), 2);
+        assert!(meta.extern_paths.contains(&display_path));
+        assert!(meta.extern_paths.contains(&inner_path));
+    }
+
+    #[test]
+    fn resolve_externs_loads_a_different_format_than_the_root_file() {
+        let root_dir = unique_temp_path("extern_cross_format_root");
+        fs::create_dir_all(&root_dir).unwrap();
+
+        let root_path = root_dir.join("config.yml");
+        fs::write(&root_path, "display: extern\n").unwrap();
+
+        // No `display/config.*` subdir exists, so `load_extern` falls through to the sibling
+        // `display.toml` even though the root file that referenced it is YAML.
+        let display_path = root_dir.join("display.toml");
+        fs::write(&display_path, "brightness = 0.5\n").unwrap();
+
+        let (value, meta) = load_value(&root_path).unwrap();
+        let _ = fs::remove_dir_all(&root_dir);
+
+        let display = Value::map_get(value.as_map().unwrap(), "display").unwrap();
+        let brightness = Value::map_get(display.as_map().unwrap(), "brightness").unwrap();
+        assert_eq!(brightness, &Value::Real(0.5));
+        assert_eq!(meta.extern_paths, vec![display_path]);
+    }
+
+    config!(EmbeddedTestConfig from_embedded "testdata/embedded_default.yml", {
+        note: String = "compiled-in default".to_string(),
+    });
+
+    #[test]
+    fn from_file_falls_back_to_embedded_default_when_path_is_missing() {
+        let path = unique_temp_path("embedded_default_missing.yml");
+        let _ = fs::remove_file(&path);
+
+        let config = EmbeddedTestConfig::from_file(&path).unwrap();
+
+        assert_eq!(config.note, "from the embedded file");
+    }
+}